@@ -1,20 +1,138 @@
+use std::collections::{HashMap, HashSet};
+
+use gloo::timers::callback::Timeout;
 use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
 use web_sys::HtmlInputElement;
 use yew::prelude::*;
 use yew_agent::{Bridge, Bridged};
 
 use crate::services::event_bus::EventBus;
-use crate::{services::websocket::WebsocketService, User};
+use crate::services::websocket::{ConnectionStatus, WebsocketService};
+use crate::User;
 
 pub enum Msg {
     HandleMsg(String),
     SubmitMessage,
+    JoinRoom(String),
+    Input,
+    StopTyping,
+    TypingCooldown,
+    TypingExpired(String),
+    StatusChanged(ConnectionStatus),
+    OpenDm(String),
+    CloseDm,
+}
+
+impl ConnectionStatus {
+    /// Human-readable label and banner colour for this state.
+    fn banner(self) -> (&'static str, &'static str) {
+        match self {
+            ConnectionStatus::Connecting => ("Connecting…", "#F39C12"),
+            ConnectionStatus::Connected => ("Connected", "#27AE60"),
+            ConnectionStatus::Disconnected => ("Disconnected", "#C0392B"),
+            ConnectionStatus::Reconnecting => ("Reconnecting…", "#E67E22"),
+        }
+    }
 }
 
 #[derive(Deserialize)]
 struct MessageData {
     from: String,
     message: String,
+    /// Epoch milliseconds of when the message was sent; older servers may omit it.
+    #[serde(default)]
+    timestamp: Option<f64>,
+    /// Set on direct messages to the recipient's username.
+    #[serde(default)]
+    to: Option<String>,
+}
+
+/// Does this URL point at an inline-renderable image?
+fn is_image_url(url: &str) -> bool {
+    let lower = url.to_lowercase();
+    lower.ends_with(".gif") || lower.ends_with(".png") || lower.ends_with(".jpg")
+}
+
+/// Render a bare `http(s)://` URL as an inline image or a clickable link.
+fn render_url(url: &str) -> Html {
+    if is_image_url(url) {
+        html! { <img class="mt-2 rounded" src={url.to_string()} style="max-width: 100%; height: auto;"/> }
+    } else {
+        html! { <a href={url.to_string()} target="_blank" rel="noopener noreferrer" style="color: #2471A3; text-decoration: underline;">{url.to_string()}</a> }
+    }
+}
+
+/// Render a message body as safe `Html`.
+///
+/// Text is emitted through Yew text nodes, which escape it for us, so no raw
+/// markup from the wire ever reaches the DOM. On top of that we parse a small
+/// markdown subset — `**bold**`, `*italic*`, `` `code` `` — and autolink any
+/// `http(s)://` URL (images inline, everything else as a link).
+fn render_message(text: &str) -> Html {
+    let bytes = text.as_bytes();
+    let mut nodes: Vec<Html> = Vec::new();
+    let mut plain = String::new();
+    let mut i = 0;
+
+    // Flush accumulated plain text as an escaped text node.
+    macro_rules! flush {
+        ($plain:expr, $nodes:expr) => {
+            if !$plain.is_empty() {
+                $nodes.push(html! { { std::mem::take(&mut $plain) } });
+            }
+        };
+    }
+
+    while i < bytes.len() {
+        let rest = &text[i..];
+        if rest.starts_with("http://") || rest.starts_with("https://") {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            flush!(plain, nodes);
+            nodes.push(render_url(&rest[..end]));
+            i += end;
+        } else if let Some(inner) = delimited(rest, "**") {
+            flush!(plain, nodes);
+            nodes.push(html! { <strong>{ render_message(inner) }</strong> });
+            i += inner.len() + 4;
+        } else if let Some(inner) = delimited(rest, "*") {
+            flush!(plain, nodes);
+            nodes.push(html! { <em>{ render_message(inner) }</em> });
+            i += inner.len() + 2;
+        } else if let Some(inner) = delimited(rest, "`") {
+            flush!(plain, nodes);
+            nodes.push(html! { <code style="background-color: #F2D7A3; padding: 1px 4px; border-radius: 4px;">{ inner.to_string() }</code> });
+            i += inner.len() + 2;
+        } else {
+            let ch = rest.chars().next().unwrap();
+            plain.push(ch);
+            i += ch.len_utf8();
+        }
+    }
+    flush!(plain, nodes);
+
+    html! { { for nodes.into_iter() } }
+}
+
+/// If `text` opens with `marker`, return the slice up to the next `marker`.
+fn delimited<'a>(text: &'a str, marker: &str) -> Option<&'a str> {
+    let after = text.strip_prefix(marker)?;
+    let end = after.find(marker)?;
+    if end == 0 {
+        return None; // empty span, e.g. "**" on its own
+    }
+    Some(&after[..end])
+}
+
+/// Format an epoch-millis timestamp into a short `HH:MM` clock label.
+fn format_clock(timestamp: Option<f64>) -> String {
+    match timestamp {
+        Some(ms) => {
+            let date = js_sys::Date::new(&JsValue::from_f64(ms));
+            format!("{:02}:{:02}", date.get_hours(), date.get_minutes())
+        }
+        None => String::new(),
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -23,6 +141,11 @@ pub enum MsgTypes {
     Users,
     Register,
     Message,
+    JoinRoom,
+    LeaveRoom,
+    Typing,
+    DirectMessage,
+    Admin,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -31,8 +154,25 @@ struct WebSocketMessage {
     message_type: MsgTypes,
     data_array: Option<Vec<String>>,
     data: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    room: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    to: Option<String>,
 }
 
+/// The room every user lands in before picking another one.
+const DEFAULT_ROOM: &str = "lobby";
+
+/// Idle time after the last keystroke before we announce we stopped typing.
+const TYPING_IDLE_MS: u32 = 3000;
+
+/// How long a peer stays in the typing roster before their flag expires.
+const TYPING_EXPIRY_MS: u32 = 4000;
+
+/// Minimum gap between two start-typing frames, so fast typing sends at most
+/// one frame per window instead of one per keystroke.
+const TYPING_COOLDOWN_MS: u32 = 1500;
+
 #[derive(Clone)]
 struct UserProfile {
     name: String,
@@ -45,7 +185,122 @@ pub struct Chat {
     _producer: Box<dyn Bridge<EventBus>>,
     wss: WebsocketService,
     messages: Vec<MessageData>,
+    rooms: Vec<String>,
+    current_room: String,
+    username: String,
+    typing_users: HashSet<String>,
+    typing_expiry: HashMap<String, Timeout>,
+    typing_idle: Option<Timeout>,
+    typing_cooldown: Option<Timeout>,
+    muted: HashSet<String>,
+    status: ConnectionStatus,
+    dm_target: Option<String>,
+    pending: Vec<String>,
+}
+impl Chat {
+    /// Send a frame now if the socket is up, otherwise queue it for flush on
+    /// reconnect. Transient frames (typing) bypass this and are best-effort.
+    fn send_or_queue(&mut self, payload: String) {
+        let live = matches!(
+            self.status,
+            ConnectionStatus::Connected | ConnectionStatus::Connecting
+        );
+        if live {
+            if let Err(e) = self.wss.tx.clone().try_send(payload.clone()) {
+                log::debug!("error sending to channel: {:?}", e);
+                self.pending.push(payload);
+            }
+        } else {
+            self.pending.push(payload);
+        }
+    }
+
+    /// Drain anything queued while we were offline, oldest first.
+    fn flush_pending(&mut self) {
+        for payload in std::mem::take(&mut self.pending) {
+            if let Err(e) = self.wss.tx.clone().try_send(payload.clone()) {
+                log::debug!("error flushing queued message: {:?}", e);
+                self.pending.push(payload);
+            }
+        }
+    }
+
+    /// Routing for a typing frame: scoped to the DM recipient when a private
+    /// thread is open, otherwise to the current room — mirroring `SubmitMessage`.
+    fn typing_scope(&self) -> (Option<String>, Option<String>) {
+        match &self.dm_target {
+            Some(target) => (None, Some(target.clone())),
+            None => (Some(self.current_room.clone()), None),
+        }
+    }
+
+    /// Append a locally-generated system notice to the current thread.
+    fn push_system(&mut self, message: String) {
+        self.messages.push(MessageData {
+            from: "system".to_string(),
+            message,
+            timestamp: Some(js_sys::Date::now()),
+            to: None,
+        });
+    }
+
+    /// Ask the server to act on a user (`kick`, `cut`) via an admin frame.
+    fn send_admin(&mut self, action: &str, target: &str) {
+        let frame = WebSocketMessage {
+            message_type: MsgTypes::Admin,
+            data: Some(action.to_string()),
+            data_array: None,
+            room: Some(self.current_room.clone()),
+            to: Some(target.to_string()),
+        };
+        self.send_or_queue(serde_json::to_string(&frame).unwrap());
+    }
+
+    /// Handle a `/`-prefixed slash command. Returns `true` on any command so
+    /// the caller knows to swallow the input and re-render.
+    fn handle_command(&mut self, raw: &str) -> bool {
+        let mut parts = raw[1..].split_whitespace();
+        let command = parts.next().unwrap_or_default();
+        let arg = parts.next();
+        match command {
+            "mute" | "unmute" | "kick" | "cut" => {
+                let Some(user) = arg else {
+                    self.push_system(format!("Usage: /{} <user>", command));
+                    return true;
+                };
+                let user = user.to_string();
+                match command {
+                    "mute" => {
+                        self.muted.insert(user.clone());
+                        self.push_system(format!("Muted {}.", user));
+                    }
+                    "unmute" => {
+                        self.muted.remove(&user);
+                        self.push_system(format!("Unmuted {}.", user));
+                    }
+                    "kick" => {
+                        self.send_admin("kick", &user);
+                        self.push_system(format!("Requested kick of {}.", user));
+                    }
+                    // `/cut` is the admin-only forceful variant of kick.
+                    _ => {
+                        self.send_admin("cut", &user);
+                        self.push_system(format!("Requested cut of {}.", user));
+                    }
+                }
+            }
+            "clear" => {
+                self.messages.clear();
+                self.push_system("Cleared the conversation.".to_string());
+            }
+            other => {
+                self.push_system(format!("Unknown command: /{}", other));
+            }
+        }
+        true
+    }
 }
+
 impl Component for Chat {
     type Message = Msg;
     type Properties = ();
@@ -55,38 +310,56 @@ impl Component for Chat {
             .link()
             .context::<User>(Callback::noop())
             .expect("context to be set");
-        let wss = WebsocketService::new();
+        // Surface the socket lifecycle into the component: the service owns the
+        // reconnect loop and drives this callback as the socket opens and drops.
+        // The initial `Register` is sent from `Msg::StatusChanged` once the socket
+        // reports `Connected`, so the same path covers first connect and reconnect.
+        let wss = WebsocketService::new(ctx.link().callback(Msg::StatusChanged));
         let username = user.username.borrow().clone();
 
-        let message = WebSocketMessage {
-            message_type: MsgTypes::Register,
-            data: Some(username.to_string()),
-            data_array: None,
-        };
-
-        if let Ok(_) = wss
-            .tx
-            .clone()
-            .try_send(serde_json::to_string(&message).unwrap())
-        {
-            log::debug!("message sent successfully");
-        }
-
         Self {
             users: vec![],
             messages: vec![],
             chat_input: NodeRef::default(),
             wss,
             _producer: EventBus::bridge(ctx.link().callback(Msg::HandleMsg)),
+            rooms: vec![
+                DEFAULT_ROOM.to_string(),
+                "random".to_string(),
+                "help".to_string(),
+            ],
+            current_room: DEFAULT_ROOM.to_string(),
+            username,
+            typing_users: HashSet::new(),
+            typing_expiry: HashMap::new(),
+            typing_idle: None,
+            typing_cooldown: None,
+            muted: HashSet::new(),
+            status: ConnectionStatus::Connecting,
+            dm_target: None,
+            pending: vec![],
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Msg::HandleMsg(s) => {
+                // Any inbound frame proves the socket is live; if we had dropped,
+                // flush anything that was queued while we were offline.
+                if self.status != ConnectionStatus::Connected {
+                    self.status = ConnectionStatus::Connected;
+                    self.flush_pending();
+                }
                 let msg: WebSocketMessage = serde_json::from_str(&s).unwrap();
                 match msg.message_type {
                     MsgTypes::Users => {
+                        // Only adopt a roster that belongs to the room we are viewing;
+                        // frames without a room are treated as global and always apply.
+                        if let Some(room) = &msg.room {
+                            if room != &self.current_room {
+                                return false;
+                            }
+                        }
                         let users_from_message = msg.data_array.unwrap_or_default();
                         self.users = users_from_message
                             .iter()
@@ -102,37 +375,235 @@ impl Component for Chat {
                         return true;
                     }
                     MsgTypes::Message => {
+                        // Drop frames fanned out for a room we are not currently in.
+                        if let Some(room) = &msg.room {
+                            if room != &self.current_room {
+                                return false;
+                            }
+                        }
                         let message_data: MessageData =
                             serde_json::from_str(&msg.data.unwrap()).unwrap();
+                        // A real message clears that user's typing flag.
+                        self.typing_users.remove(&message_data.from);
+                        self.typing_expiry.remove(&message_data.from);
+                        self.messages.push(message_data);
+                        return true;
+                    }
+                    MsgTypes::DirectMessage => {
+                        // DMs are not room-scoped; the render loop filters them by
+                        // the open DM thread.
+                        let mut message_data: MessageData =
+                            serde_json::from_str(&msg.data.unwrap()).unwrap();
+                        if message_data.to.is_none() {
+                            message_data.to = msg.to;
+                        }
+                        self.typing_users.remove(&message_data.from);
+                        self.typing_expiry.remove(&message_data.from);
                         self.messages.push(message_data);
                         return true;
                     }
+                    MsgTypes::Typing => {
+                        if let Some(user) = msg.data {
+                            // Ignore our own typing echoed back by the server.
+                            if user == self.username {
+                                return false;
+                            }
+                            self.typing_users.insert(user.clone());
+                            let link = ctx.link().clone();
+                            let expired = user.clone();
+                            self.typing_expiry.insert(
+                                user,
+                                Timeout::new(TYPING_EXPIRY_MS, move || {
+                                    link.send_message(Msg::TypingExpired(expired))
+                                }),
+                            );
+                            return true;
+                        }
+                        // A stop frame lists the users who went idle.
+                        if let Some(stopped) = msg.data_array {
+                            for user in stopped {
+                                self.typing_users.remove(&user);
+                                self.typing_expiry.remove(&user);
+                            }
+                            return true;
+                        }
+                        return false;
+                    }
                     _ => {
                         return false;
                     }
                 }
             }
-            Msg::SubmitMessage => {
-                let input = self.chat_input.cast::<HtmlInputElement>();
-                if let Some(input) = input {
-                    let message_content = input.value();
-                    if message_content.trim().is_empty() { // Prevent sending empty messages
-                        return false;
-                    }
-                    let message = WebSocketMessage {
-                        message_type: MsgTypes::Message,
-                        data: Some(message_content),
+            Msg::JoinRoom(room) => {
+                if room == self.current_room {
+                    return false;
+                }
+                // Drop out of the old room first so the server can prune us from
+                // its roster, then announce the room we are switching to.
+                let leave = WebSocketMessage {
+                    message_type: MsgTypes::LeaveRoom,
+                    data: None,
+                    data_array: None,
+                    room: Some(self.current_room.clone()),
+                    to: None,
+                };
+                self.send_or_queue(serde_json::to_string(&leave).unwrap());
+                let message = WebSocketMessage {
+                    message_type: MsgTypes::JoinRoom,
+                    data: None,
+                    data_array: None,
+                    room: Some(room.clone()),
+                    to: None,
+                };
+                self.send_or_queue(serde_json::to_string(&message).unwrap());
+                // Joining a room leaves any open DM thread.
+                self.dm_target = None;
+                self.current_room = room;
+                self.messages.clear();
+                true
+            }
+            Msg::Input => {
+                // Announce that we are typing at most once per cooldown window so
+                // fast typing does not emit one frame per character; then (re)arm
+                // the idle timer that sends a stop frame once the keystrokes pause.
+                if self.typing_cooldown.is_none() {
+                    // Route presence the same way the message will go (DM vs room).
+                    let (room, to) = self.typing_scope();
+                    let typing = WebSocketMessage {
+                        message_type: MsgTypes::Typing,
+                        data: Some(self.username.clone()),
                         data_array: None,
+                        room,
+                        to,
                     };
                     if let Err(e) = self
                         .wss
                         .tx
                         .clone()
-                        .try_send(serde_json::to_string(&message).unwrap())
+                        .try_send(serde_json::to_string(&typing).unwrap())
                     {
                         log::debug!("error sending to channel: {:?}", e);
                     }
+                    let link = ctx.link().clone();
+                    self.typing_cooldown = Some(Timeout::new(TYPING_COOLDOWN_MS, move || {
+                        link.send_message(Msg::TypingCooldown)
+                    }));
+                }
+                let link = ctx.link().clone();
+                self.typing_idle = Some(Timeout::new(TYPING_IDLE_MS, move || {
+                    link.send_message(Msg::StopTyping)
+                }));
+                false
+            }
+            Msg::TypingCooldown => {
+                // Cooldown lapsed; the next keystroke may send a fresh start frame.
+                self.typing_cooldown = None;
+                false
+            }
+            Msg::StopTyping => {
+                self.typing_idle = None;
+                self.typing_cooldown = None;
+                let (room, to) = self.typing_scope();
+                let stop = WebSocketMessage {
+                    message_type: MsgTypes::Typing,
+                    data: None,
+                    data_array: Some(vec![self.username.clone()]),
+                    room,
+                    to,
+                };
+                if let Err(e) = self
+                    .wss
+                    .tx
+                    .clone()
+                    .try_send(serde_json::to_string(&stop).unwrap())
+                {
+                    log::debug!("error sending to channel: {:?}", e);
+                }
+                false
+            }
+            Msg::TypingExpired(user) => {
+                self.typing_users.remove(&user);
+                self.typing_expiry.remove(&user);
+                true
+            }
+            Msg::OpenDm(user) => {
+                if self.dm_target.as_deref() == Some(user.as_str()) {
+                    return false;
+                }
+                self.dm_target = Some(user);
+                true
+            }
+            Msg::CloseDm => {
+                // Return to the room view without discarding its history.
+                if self.dm_target.is_none() {
+                    return false;
+                }
+                self.dm_target = None;
+                true
+            }
+            Msg::StatusChanged(status) => {
+                // `WebsocketService` owns the reconnect loop and drives this as the
+                // socket opens and drops. Each time a (re)connection comes up we
+                // re-announce ourselves so the server re-adds us to the roster, then
+                // flush anything that was queued while the link was down.
+                let reconnected = status == ConnectionStatus::Connected
+                    && self.status != ConnectionStatus::Connected;
+                self.status = status;
+                if reconnected {
+                    let register = WebSocketMessage {
+                        message_type: MsgTypes::Register,
+                        data: Some(self.username.clone()),
+                        data_array: None,
+                        room: Some(self.current_room.clone()),
+                        to: None,
+                    };
+                    if let Err(e) = self
+                        .wss
+                        .tx
+                        .clone()
+                        .try_send(serde_json::to_string(&register).unwrap())
+                    {
+                        log::debug!("error re-registering: {:?}", e);
+                    }
+                    self.flush_pending();
+                }
+                true
+            }
+            Msg::SubmitMessage => {
+                let input = self.chat_input.cast::<HtmlInputElement>();
+                if let Some(input) = input {
+                    let message_content = input.value();
+                    if message_content.trim().is_empty() { // Prevent sending empty messages
+                        return false;
+                    }
+                    // Slash-commands are parsed out and handled before anything is sent.
+                    if message_content.starts_with('/') {
+                        let handled = self.handle_command(message_content.trim());
+                        input.set_value("");
+                        return handled;
+                    }
+                    // A DM is tagged with its recipient; otherwise it fans out to the room.
+                    let message = match &self.dm_target {
+                        Some(target) => WebSocketMessage {
+                            message_type: MsgTypes::DirectMessage,
+                            data: Some(message_content),
+                            data_array: None,
+                            room: None,
+                            to: Some(target.clone()),
+                        },
+                        None => WebSocketMessage {
+                            message_type: MsgTypes::Message,
+                            data: Some(message_content),
+                            data_array: None,
+                            room: Some(self.current_room.clone()),
+                            to: None,
+                        },
+                    };
+                    self.send_or_queue(serde_json::to_string(&message).unwrap());
                     input.set_value("");
+                    // Sending a message implies we stopped typing.
+                    self.typing_idle = None;
+                    self.typing_cooldown = None;
                 };
                 false
             }
@@ -149,11 +620,33 @@ impl Component for Chat {
             <div class="flex w-screen">
                 // Sidebar - Blue
                 <div class="flex-none w-56 h-screen" style="background-color: #3498DB;"> // Blue background
+                    <div class="text-xl p-3" style="color: white;">{"Rooms"}</div> // White text
+                    {
+                        self.rooms.clone().iter().map(|r| {
+                            let room = r.clone();
+                            let onclick = ctx.link().callback(move |_| Msg::JoinRoom(room.clone()));
+                            // Highlight the room we are currently viewing.
+                            let active = r == &self.current_room;
+                            let bg = if active { "#1B4F72" } else { "#2E86C1" };
+                            html!{
+                                <div class="flex m-3 rounded-lg p-2" style={format!("background-color: {}; cursor: pointer;", bg)} {onclick}>
+                                    <div class="flex-grow p-1" style="color: white;">
+                                        {format!("# {}", r)}
+                                    </div>
+                                </div>
+                            }
+                        }).collect::<Html>()
+                    }
                     <div class="text-xl p-3" style="color: white;">{"Users"}</div> // White text
                     {
                         self.users.clone().iter().map(|u| {
+                            let name = u.name.clone();
+                            let onclick = ctx.link().callback(move |_| Msg::OpenDm(name.clone()));
+                            // Highlight the user whose DM thread is open.
+                            let active = self.dm_target.as_deref() == Some(u.name.as_str());
+                            let bg = if active { "#AED6F1" } else { "#EBF5FB" };
                             html!{
-                                <div class="flex m-3 rounded-lg p-2" style="background-color: #EBF5FB;"> // Light blue background for user item
+                                <div class="flex m-3 rounded-lg p-2" style={format!("background-color: {}; cursor: pointer;", bg)} {onclick}> // Light blue background for user item
                                     <div>
                                         <img class="w-12 h-12 rounded-full" src={u.avatar.clone()} alt="avatar"/>
                                     </div>
@@ -174,40 +667,132 @@ impl Component for Chat {
                 // Main Content Area
                 <div class="grow h-screen flex flex-col" style="background-color: #F4F6F6;"> // Light grey background for main chat area
                     // Chat Header - Blue background, Orange border
-                    <div class="w-full h-14 flex items-center p-3" style="background-color: #3498DB; border-bottom: 3px solid #F39C12;"> // Blue background, Orange bottom border
-                        <div class="text-xl" style="color: white;">{"ðŸ’¬ Chat!"}</div> // White text
+                    <div class="w-full h-14 flex items-center justify-between p-3" style="background-color: #3498DB; border-bottom: 3px solid #F39C12;"> // Blue background, Orange bottom border
+                        <div class="flex items-center text-xl" style="color: white;">
+                            if self.dm_target.is_some() {
+                                // Leave the DM thread and drop back to the room,
+                                // keeping the room's messages intact.
+                                <button onclick={ctx.link().callback(|_| Msg::CloseDm)}
+                                        class="mr-3 rounded-full px-2"
+                                        style="background-color: #2E86C1; color: white; border: none; cursor: pointer;"
+                                        title={format!("Back to #{}", self.current_room)}>
+                                    {"â€¹"}
+                                </button>
+                            }
+                            {
+                                match &self.dm_target {
+                                    Some(target) => format!("ðŸ’¬ {}", target),
+                                    None => format!("ðŸ’¬ #{}", self.current_room),
+                                }
+                            }
+                        </div> // White text
+                        {
+                            // Connection-status banner reflecting the socket state machine.
+                            let (label, color) = self.status.banner();
+                            html!{
+                                <div class="flex items-center text-sm rounded-full px-3 py-1" style={format!("background-color: {}; color: white;", color)}>
+                                    <span class="w-2 h-2 rounded-full mr-2" style="background-color: white;"></span>
+                                    {label}
+                                </div>
+                            }
+                        }
                     </div>
 
                     // Messages Area - Light background, Orange border for consistency
                     <div class="w-full grow overflow-y-auto p-4" style="border-bottom: 2px solid #F39C12;"> // Added padding, overflow-y
                         {
-                            self.messages.iter().map(|m| {
+                            // When a DM thread is open, show only that private conversation;
+                            // otherwise show the room's public messages.
+                            let me = self.username.as_str();
+                            let visible: Vec<&MessageData> = self.messages.iter().filter(|m| {
+                                // Locally-muted senders never show; system notices always do.
+                                if self.muted.contains(&m.from) {
+                                    return false;
+                                }
+                                if m.from == "system" {
+                                    return true;
+                                }
+                                match &self.dm_target {
+                                    Some(target) => {
+                                        m.to.is_some()
+                                            && ((m.from == *target && m.to.as_deref() == Some(me))
+                                                || (m.from == me && m.to.as_deref() == Some(target.as_str())))
+                                    }
+                                    None => m.to.is_none(),
+                                }
+                            }).collect();
+
+                            visible.iter().enumerate().map(|(i, m)| {
+                                // Collapse consecutive messages from the same sender into a
+                                // single avatar+name header followed by stacked bubbles.
+                                let first_of_run = i == 0 || visible[i - 1].from != m.from;
+                                let is_dm = m.to.is_some();
                                 let user_profile = self.users.iter().find(|u| u.name == m.from);
                                 let avatar_src = user_profile.map_or_else(
                                     || format!("https://avatars.dicebear.com/api/initials/{}.svg", m.from), // Fallback avatar
                                     |user| user.avatar.clone()
                                 );
+                                // DMs get a distinct bubble colour and a to/from label.
+                                let bubble_style = if is_dm {
+                                    "background-color: #D6EAF8; border-radius: 8px; padding: 10px; max-width: 70%;"
+                                } else {
+                                    "background-color: #FDEBD0; border-radius: 8px; padding: 10px; max-width: 70%;"
+                                };
+                                let dm_label = if is_dm {
+                                    if m.from == me {
+                                        m.to.clone().map(|t| format!("to {}", t))
+                                    } else {
+                                        Some(format!("from {}", m.from))
+                                    }
+                                } else {
+                                    None
+                                };
 
                                 html!{
-                                    // Message Bubble - Light Orange
-                                    <div class="flex items-start mb-4"> // Changed items-end to items-start for typical chat layout
-                                        <img class="w-10 h-10 rounded-full mr-3" src={avatar_src} alt="avatar"/>
-                                        <div style="background-color: #FDEBD0; border-radius: 8px; padding: 10px; max-width: 70%;"> // Light orange background for message
-                                            <div class="text-sm font-semibold" style="color: #D35400; margin-bottom: 4px;"> // Orange, slightly darker for sender name
-                                                {m.from.clone()}
-                                            </div>
+                                    // Message Bubble - Light Orange (light blue for DMs)
+                                    <div class={if first_of_run { "flex items-start mt-4" } else { "flex items-start mt-1" }}>
+                                        if first_of_run {
+                                            <img class="w-10 h-10 rounded-full mr-3" src={avatar_src} alt="avatar"/>
+                                        } else {
+                                            <div class="w-10 mr-3"></div> // Align stacked bubbles under the avatar
+                                        }
+                                        <div style={bubble_style}>
+                                            if first_of_run {
+                                                <div class="flex text-sm font-semibold justify-between" style="color: #D35400; margin-bottom: 4px;"> // Orange, slightly darker for sender name
+                                                    <span>
+                                                        {m.from.clone()}
+                                                        if let Some(label) = dm_label {
+                                                            <span class="text-xs font-normal ml-2" style="color: #2471A3;">{format!("({})", label)}</span>
+                                                        }
+                                                    </span>
+                                                    <span class="text-xs font-normal ml-3" style="color: #B9770E;">{format_clock(m.timestamp)}</span>
+                                                </div>
+                                            }
                                             <div class="text-sm" style="color: #333333; word-wrap: break-word;"> // Dark grey/black text for message
-                                                if m.message.ends_with(".gif") {
-                                                    <img class="mt-2 rounded" src={m.message.clone()} style="max-width: 100%; height: auto;"/>
-                                                } else {
-                                                    {m.message.clone()}
-                                                }
+                                                { render_message(&m.message) }
                                             </div>
                                         </div>
                                     </div>
                                 }
                             }).collect::<Html>()
                         }
+                        {
+                            // Live "… is typing" line driven by the transient per-user flags.
+                            if self.typing_users.is_empty() {
+                                html!{}
+                            } else {
+                                let mut names: Vec<String> = self.typing_users.iter().cloned().collect();
+                                names.sort();
+                                let label = match names.as_slice() {
+                                    [one] => format!("{} is typing…", one),
+                                    [a, b] => format!("{} and {} are typing…", a, b),
+                                    _ => "Several people are typing…".to_string(),
+                                };
+                                html!{
+                                    <div class="text-xs italic mt-2" style="color: #7F8C8D;">{label}</div>
+                                }
+                            }
+                        }
                     </div>
 
                     // Input Area - Light grey bar, Orange button
@@ -216,6 +801,7 @@ impl Component for Chat {
                                class="block w-full py-2 pl-4 pr-4 mx-2 rounded-full outline-none focus:border-blue-500" // Kept focus:border for visual cue if CSS is ever added
                                style="background-color: #FFFFFF; border: 1px solid #BCCCDC; color: #2C3E50; height: 40px;" // White input, light blue/grey border
                                name="message" required=true
+                               oninput={ctx.link().callback(|_: InputEvent| Msg::Input)}
                                onkeypress={ctx.link().batch_callback(|e: KeyboardEvent| {
                                    if e.key() == "Enter" {
                                        Some(Msg::SubmitMessage)
@@ -236,4 +822,45 @@ impl Component for Chat {
             </div>
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delimited_extracts_the_inner_span() {
+        assert_eq!(delimited("**bold** rest", "**"), Some("bold"));
+        assert_eq!(delimited("*italic* x", "*"), Some("italic"));
+        assert_eq!(delimited("`code`", "`"), Some("code"));
+        // Stops at the first closing marker.
+        assert_eq!(delimited("*a* *b*", "*"), Some("a"));
+    }
+
+    #[test]
+    fn delimited_rejects_empty_and_unterminated_spans() {
+        // An empty span such as a lone "**" must not match.
+        assert_eq!(delimited("****", "**"), None);
+        assert_eq!(delimited("**", "*"), None);
+        // No closing marker.
+        assert_eq!(delimited("**bold", "**"), None);
+        // Marker not at the start of the slice.
+        assert_eq!(delimited("x **b**", "**"), None);
+    }
+
+    #[test]
+    fn format_clock_without_timestamp_is_blank() {
+        // Older servers omit the timestamp; the label falls back to empty.
+        assert_eq!(format_clock(None), "");
+    }
+
+    #[test]
+    fn render_message_survives_edge_cases() {
+        // Empty spans, an unterminated marker, an autolink running to the
+        // whitespace boundary, and an empty body must all render without panic.
+        let _ = render_message("**");
+        let _ = render_message("a * b");
+        let _ = render_message("see https://example.com/x now");
+        let _ = render_message("");
+    }
 }
\ No newline at end of file
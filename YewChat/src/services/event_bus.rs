@@ -0,0 +1,51 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use yew_agent::{Agent, AgentLink, Context, HandlerId};
+
+/// Input accepted by the [`EventBus`]: a raw frame to fan out to subscribers.
+#[derive(Serialize, Deserialize, Debug)]
+pub enum Request {
+    EventBusMsg(String),
+}
+
+/// A context agent that relays websocket frames from `WebsocketService` to every
+/// subscribed component (currently just `Chat`).
+pub struct EventBus {
+    link: AgentLink<EventBus>,
+    subscribers: HashSet<HandlerId>,
+}
+
+impl Agent for EventBus {
+    type Reach = Context<Self>;
+    type Message = ();
+    type Input = Request;
+    type Output = String;
+
+    fn create(link: AgentLink<Self>) -> Self {
+        Self {
+            link,
+            subscribers: HashSet::new(),
+        }
+    }
+
+    fn update(&mut self, _msg: Self::Message) {}
+
+    fn handle_input(&mut self, msg: Self::Input, _id: HandlerId) {
+        match msg {
+            Request::EventBusMsg(s) => {
+                for sub in self.subscribers.iter() {
+                    self.link.respond(*sub, s.clone());
+                }
+            }
+        }
+    }
+
+    fn connected(&mut self, id: HandlerId) {
+        self.subscribers.insert(id);
+    }
+
+    fn disconnected(&mut self, id: HandlerId) {
+        self.subscribers.remove(&id);
+    }
+}
@@ -0,0 +1,114 @@
+use futures::channel::mpsc::{Receiver, Sender};
+use futures::{select, SinkExt, StreamExt};
+use gloo::timers::future::TimeoutFuture;
+use gloo_net::websocket::{futures::WebSocket, Message};
+use wasm_bindgen_futures::spawn_local;
+use yew::Callback;
+use yew_agent::Dispatched;
+
+use super::event_bus::{EventBus, Request};
+
+/// Address of the chat server's websocket endpoint.
+const WS_URL: &str = "ws://127.0.0.1:8080";
+
+/// Lifecycle of the underlying websocket, surfaced to the UI so it can reflect
+/// whether frames are flowing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionStatus {
+    Connecting,
+    Connected,
+    Disconnected,
+    Reconnecting,
+}
+
+/// Deterministic part of the reconnect backoff: 1s, 2s, 4s … capped at 30s.
+/// Jitter is added on top in [`backoff_delay_ms`].
+fn backoff_base_ms(attempt: u32) -> u32 {
+    1000u32.saturating_mul(1 << attempt.min(5)).min(30_000)
+}
+
+/// Reconnect backoff for `attempt` (1-based): the capped base plus up to 1s of
+/// jitter so a fleet of clients doesn't reconnect in lockstep.
+fn backoff_delay_ms(attempt: u32) -> u32 {
+    backoff_base_ms(attempt.saturating_sub(1)) + (js_sys::Math::random() * 1000.0) as u32
+}
+
+/// Owns the websocket and keeps it alive across drops.
+///
+/// Outbound frames are written to [`tx`](Self::tx); a background task pumps them
+/// to the socket and relays inbound frames onto the [`EventBus`]. When the socket
+/// closes, the task reopens it with exponential backoff — re-wiring the event bus
+/// and re-emitting the status callback — while buffered outbound frames wait on
+/// the channel and flush automatically once the link is back.
+pub struct WebsocketService {
+    pub tx: Sender<String>,
+}
+
+impl WebsocketService {
+    pub fn new(status: Callback<ConnectionStatus>) -> Self {
+        let (tx, rx) = futures::channel::mpsc::channel::<String>(1000);
+        spawn_local(supervise(rx, status));
+        Self { tx }
+    }
+}
+
+/// Connect, pump both directions until the socket drops, then reconnect with
+/// backoff — indefinitely, until the owning `WebsocketService` is dropped (which
+/// closes `rx`).
+async fn supervise(mut rx: Receiver<String>, status: Callback<ConnectionStatus>) {
+    let mut attempt: u32 = 0;
+    loop {
+        status.emit(if attempt == 0 {
+            ConnectionStatus::Connecting
+        } else {
+            ConnectionStatus::Reconnecting
+        });
+
+        if let Ok(ws) = WebSocket::open(WS_URL) {
+            status.emit(ConnectionStatus::Connected);
+            attempt = 0;
+            let (mut write, mut read) = ws.split();
+            loop {
+                select! {
+                    outgoing = rx.next() => match outgoing {
+                        // A frame to send; a write error means the socket died.
+                        Some(msg) => {
+                            if write.send(Message::Text(msg)).await.is_err() {
+                                break;
+                            }
+                        }
+                        // Channel closed: the service was dropped, so stop for good.
+                        None => return,
+                    },
+                    incoming = read.next() => match incoming {
+                        Some(Ok(Message::Text(data))) => {
+                            EventBus::dispatcher().send(Request::EventBusMsg(data));
+                        }
+                        Some(Ok(Message::Bytes(_))) => {}
+                        // Error or end-of-stream: the socket closed.
+                        Some(Err(_)) | None => break,
+                    },
+                }
+            }
+        }
+
+        status.emit(ConnectionStatus::Disconnected);
+        attempt = attempt.saturating_add(1);
+        TimeoutFuture::new(backoff_delay_ms(attempt)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_then_caps() {
+        assert_eq!(backoff_base_ms(0), 1000);
+        assert_eq!(backoff_base_ms(1), 2000);
+        assert_eq!(backoff_base_ms(2), 4000);
+        // Capped from attempt 5 onwards (1000 * 32 = 32000 -> 30000).
+        assert_eq!(backoff_base_ms(5), 30_000);
+        assert_eq!(backoff_base_ms(50), 30_000);
+    }
+}